@@ -10,7 +10,7 @@ fn main() -> Result<()> {
     let mut consecutive_eos = 0;
     
     let t_main_start = ggml_time_us();
-    let mut decoder = encoding_rs::UTF_8.new_decoder();
+    let mut detokenizer = LlamaDetokenizer::new(&model, Special::Tokenize).with_eos_to_newline(true);
     let mut sampler = LlamaSampler::chain_simple([
         LlamaSampler::dist(seed.unwrap_or(1234)),
         LlamaSampler::greedy(),
@@ -32,10 +32,8 @@ fn main() -> Result<()> {
             consecutive_eos = 0;
         }
 
-        let output_bytes = model.token_to_bytes(token, Special::Tokenize)?;
-        let mut output_string = String::with_capacity(32);
-        let _decode_result = decoder.decode_to_string(&output_bytes, &mut output_string, false);
-        
+        let output_string = detokenizer.push(token);
+
         if !output_string.is_empty() {
             print!("{output_string}");
             std::io::stdout().flush()?;
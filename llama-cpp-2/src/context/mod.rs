@@ -0,0 +1,118 @@
+//! A safe wrapper around `llama_context`.
+use std::ptr::NonNull;
+
+use crate::llama_batch::LlamaBatch;
+use crate::model::LlamaModel;
+
+pub mod params;
+
+/// Errors that can occur during [`LlamaContext::decode`].
+#[derive(Debug, thiserror::Error)]
+pub enum DecodeError {
+    /// `llama_decode` returned `1`; the KV cache slot could not be found.
+    #[error("decode returned {0}, could not find a kv cache slot")]
+    NoKvCacheSlot(i32),
+    /// `llama_decode` returned any other nonzero error code.
+    #[error("decode returned {0}")]
+    Error(i32),
+}
+
+/// Errors that can occur while reading embeddings out of a context.
+#[derive(Debug, thiserror::Error)]
+pub enum EmbeddingsError {
+    /// The context was not created with `embeddings` enabled, or the last decode didn't request
+    /// embeddings for this output.
+    #[error("no embeddings are available, was the context created with embeddings enabled?")]
+    NotAvailable,
+}
+
+/// Errors that can occur while reading logits out of a context.
+#[derive(Debug, thiserror::Error)]
+pub enum LogitsError {
+    /// `llama_get_logits_ith` returned null, meaning logits weren't requested for this output
+    /// index (the batch entry wasn't staged with `logits = true`).
+    #[error("no logits are available for output index {0}, was `logits` requested for it?")]
+    NotAvailable(i32),
+}
+
+/// A safe wrapper around `llama_context`.
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct LlamaContext<'model> {
+    pub(crate) context: NonNull<llama_cpp_sys_2::llama_context>,
+    /// The model this context was created from. Keeping it borrowed ensures the model outlives
+    /// the context, as required by llama.cpp.
+    pub model: &'model LlamaModel,
+}
+
+impl<'model> LlamaContext<'model> {
+    /// Decode a batch, running the model forward and filling in any requested logits.
+    ///
+    /// # Errors
+    /// Returns a [`DecodeError`] if `llama_decode` reports a failure.
+    pub fn decode(&mut self, batch: &mut LlamaBatch) -> Result<(), DecodeError> {
+        let result = unsafe {
+            llama_cpp_sys_2::llama_decode(self.context.as_ptr(), batch.handle())
+        };
+        match result {
+            0 => Ok(()),
+            1 => Err(DecodeError::NoKvCacheSlot(result)),
+            _ => Err(DecodeError::Error(result)),
+        }
+    }
+    /// Get a mutable view of the logits produced for the `i`-th output of the last decode, e.g.
+    /// to apply a logit bias before sampling.
+    ///
+    /// # Errors
+    /// Returns [`LogitsError::NotAvailable`] if `llama_get_logits_ith` reports no logits for that
+    /// output index, for example if the batch entry wasn't staged with `logits = true`.
+    pub fn logits_ith_mut(&mut self, i: i32) -> Result<&mut [f32], LogitsError> {
+        let ptr = unsafe { llama_cpp_sys_2::llama_get_logits_ith(self.context.as_ptr(), i) };
+        if ptr.is_null() {
+            return Err(LogitsError::NotAvailable(i));
+        }
+        let n_vocab = usize::try_from(self.model.n_vocab()).expect("n_vocab fits into a usize");
+        Ok(unsafe { std::slice::from_raw_parts_mut(ptr, n_vocab) })
+    }
+
+    /// Get the pooled embedding for sequence `seq_id` from the last decode.
+    ///
+    /// Only meaningful when the context was created with a pooling type other than
+    /// [`params::LlamaPoolingType::None`]; for per-token embeddings use [`Self::embeddings_ith`].
+    ///
+    /// # Errors
+    /// Returns [`EmbeddingsError::NotAvailable`] if no pooled embedding is available, for example
+    /// if the context was not created with embeddings enabled.
+    pub fn embeddings(&self, seq_id: llama_cpp_sys_2::llama_seq_id) -> Result<&[f32], EmbeddingsError> {
+        let ptr = unsafe {
+            llama_cpp_sys_2::llama_get_embeddings_seq(self.context.as_ptr(), seq_id)
+        };
+        if ptr.is_null() {
+            return Err(EmbeddingsError::NotAvailable);
+        }
+        let n_embd = usize::try_from(self.model.n_embd()).expect("n_embd fits into a usize");
+        Ok(unsafe { std::slice::from_raw_parts(ptr, n_embd) })
+    }
+
+    /// Get the embedding of the `i`-th token with requested logits/embeddings from the last
+    /// decode, i.e. per-token embeddings when the context's pooling type is
+    /// [`params::LlamaPoolingType::None`].
+    ///
+    /// # Errors
+    /// Returns [`EmbeddingsError::NotAvailable`] if no embedding is available for that output
+    /// index.
+    pub fn embeddings_ith(&self, i: i32) -> Result<&[f32], EmbeddingsError> {
+        let ptr = unsafe { llama_cpp_sys_2::llama_get_embeddings_ith(self.context.as_ptr(), i) };
+        if ptr.is_null() {
+            return Err(EmbeddingsError::NotAvailable);
+        }
+        let n_embd = usize::try_from(self.model.n_embd()).expect("n_embd fits into a usize");
+        Ok(unsafe { std::slice::from_raw_parts(ptr, n_embd) })
+    }
+}
+
+impl Drop for LlamaContext<'_> {
+    fn drop(&mut self) {
+        unsafe { llama_cpp_sys_2::llama_free(self.context.as_ptr()) }
+    }
+}
@@ -0,0 +1,103 @@
+//! Parameters used to create a [`super::LlamaContext`].
+
+/// How token embeddings are pooled into a single sequence embedding, mirroring
+/// `llama_pooling_type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LlamaPoolingType {
+    /// Use whatever pooling the model itself specifies.
+    #[default]
+    Unspecified,
+    /// No pooling -- return the embedding of every token.
+    None,
+    /// Average all token embeddings.
+    Mean,
+    /// Use the embedding of the `CLS` token.
+    Cls,
+    /// Use the embedding of the last token.
+    Last,
+}
+
+impl From<LlamaPoolingType> for llama_cpp_sys_2::llama_pooling_type {
+    fn from(value: LlamaPoolingType) -> Self {
+        match value {
+            LlamaPoolingType::Unspecified => llama_cpp_sys_2::LLAMA_POOLING_TYPE_UNSPECIFIED,
+            LlamaPoolingType::None => llama_cpp_sys_2::LLAMA_POOLING_TYPE_NONE,
+            LlamaPoolingType::Mean => llama_cpp_sys_2::LLAMA_POOLING_TYPE_MEAN,
+            LlamaPoolingType::Cls => llama_cpp_sys_2::LLAMA_POOLING_TYPE_CLS,
+            LlamaPoolingType::Last => llama_cpp_sys_2::LLAMA_POOLING_TYPE_LAST,
+        }
+    }
+}
+
+impl From<llama_cpp_sys_2::llama_pooling_type> for LlamaPoolingType {
+    /// Any value llama.cpp doesn't define yet (e.g. a pooling type added by a newer llama.cpp than
+    /// this crate knows about) maps to [`Self::Unspecified`], the same default used when no
+    /// pooling type is configured at all.
+    fn from(value: llama_cpp_sys_2::llama_pooling_type) -> Self {
+        match value {
+            llama_cpp_sys_2::LLAMA_POOLING_TYPE_NONE => Self::None,
+            llama_cpp_sys_2::LLAMA_POOLING_TYPE_MEAN => Self::Mean,
+            llama_cpp_sys_2::LLAMA_POOLING_TYPE_CLS => Self::Cls,
+            llama_cpp_sys_2::LLAMA_POOLING_TYPE_LAST => Self::Last,
+            _ => Self::Unspecified,
+        }
+    }
+}
+
+/// Safe wrapper around `llama_context_params`.
+#[derive(Debug, Clone)]
+pub struct LlamaContextParams {
+    pub(crate) context_params: llama_cpp_sys_2::llama_context_params,
+}
+
+impl Default for LlamaContextParams {
+    fn default() -> Self {
+        Self {
+            context_params: unsafe { llama_cpp_sys_2::llama_context_default_params() },
+        }
+    }
+}
+
+impl LlamaContextParams {
+    /// Set the size of the context window.
+    #[must_use]
+    pub fn with_n_ctx(mut self, n_ctx: Option<std::num::NonZeroU32>) -> Self {
+        self.context_params.n_ctx = n_ctx.map_or(0, std::num::NonZeroU32::get);
+        self
+    }
+
+    /// The size of the context window, or `0` if it is taken from the model.
+    #[must_use]
+    pub fn n_ctx(&self) -> u32 {
+        self.context_params.n_ctx
+    }
+
+    /// Set how token embeddings should be pooled. Defaults to [`LlamaPoolingType::Unspecified`],
+    /// which defers to the model's own configuration.
+    #[must_use]
+    pub fn with_pooling_type(mut self, pooling_type: LlamaPoolingType) -> Self {
+        self.context_params.pooling_type = pooling_type.into();
+        self
+    }
+
+    /// The configured pooling type.
+    #[must_use]
+    pub fn pooling_type(&self) -> LlamaPoolingType {
+        self.context_params.pooling_type.into()
+    }
+
+    /// Set whether the context computes embeddings in addition to logits. Defaults to `false`;
+    /// must be set to use [`super::LlamaContext::embeddings`]/[`super::LlamaContext::embeddings_ith`],
+    /// which otherwise always return [`super::EmbeddingsError::NotAvailable`].
+    #[must_use]
+    pub fn with_embeddings(mut self, embeddings: bool) -> Self {
+        self.context_params.embeddings = embeddings;
+        self
+    }
+
+    /// Whether the context is configured to compute embeddings.
+    #[must_use]
+    pub fn embeddings(&self) -> bool {
+        self.context_params.embeddings
+    }
+}
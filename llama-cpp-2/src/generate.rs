@@ -0,0 +1,251 @@
+//! A high-level, ergonomic streaming generation API, wrapping the lower-level decode/sample/
+//! detokenize loop that the `simple` example hand-writes. [`GenerationSession::generate`] also
+//! handles tokenizing the prompt and decoding it, so callers don't have to build that boilerplate
+//! themselves.
+use std::collections::HashMap;
+
+use crate::context::LlamaContext;
+use crate::context::DecodeError;
+use crate::llama_batch::LlamaBatch;
+use crate::model::{AddBos, LlamaModel, Special};
+use crate::sampling::LlamaSampler;
+use crate::token::detokenizer::LlamaDetokenizer;
+use crate::token::LlamaToken;
+use crate::StringToTokenError;
+
+/// Options controlling a single [`GenerationSession::generate`] (or [`GenerationSession::new`])
+/// call.
+#[derive(Debug, Clone)]
+pub struct GenerateOptions {
+    max_tokens: Option<usize>,
+    stop: Vec<String>,
+    logit_bias: HashMap<LlamaToken, f32>,
+    seed: u32,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        Self {
+            max_tokens: None,
+            stop: Vec::new(),
+            logit_bias: HashMap::new(),
+            seed: 1234,
+        }
+    }
+}
+
+impl GenerateOptions {
+    /// Stop generating after at most `max_tokens` tokens.
+    #[must_use]
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Stop generating as soon as the accumulated output ends with `stop`.
+    #[must_use]
+    pub fn with_stop(mut self, stop: impl Into<String>) -> Self {
+        self.stop.push(stop.into());
+        self
+    }
+
+    /// Add a bias to a token's logit before sampling; positive values make the token more likely,
+    /// negative values (including `f32::NEG_INFINITY`) make it less likely or impossible.
+    #[must_use]
+    pub fn with_logit_bias(mut self, token: LlamaToken, bias: f32) -> Self {
+        self.logit_bias.insert(token, bias);
+        self
+    }
+
+    /// Biases every token in [`LlamaModel::eog_tokens`] to `f32::NEG_INFINITY`: generation will
+    /// never stop on an end-of-generation token, only on `max_tokens` or a configured stop
+    /// sequence.
+    #[must_use]
+    pub fn ignore_eos(mut self, model: &LlamaModel) -> Self {
+        for token in model.eog_tokens() {
+            self = self.with_logit_bias(token, f32::NEG_INFINITY);
+        }
+        self
+    }
+
+    /// The seed used for sampling.
+    #[must_use]
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// An error produced while streaming generation.
+#[derive(Debug, thiserror::Error)]
+pub enum GenerateError {
+    /// Decoding the prompt or a generated token failed.
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+    /// Staging a token into the batch failed.
+    #[error(transparent)]
+    BatchAdd(#[from] crate::llama_batch::BatchAddError),
+    /// Tokenizing the prompt failed.
+    #[error(transparent)]
+    Tokenize(#[from] StringToTokenError),
+}
+
+/// A single in-progress text generation, driven token-by-token via [`Iterator`].
+///
+/// Each item is a UTF-8-complete piece of text (possibly empty, if a token only contributed to a
+/// buffered partial byte sequence). Iteration stops once `max_tokens` is reached, a configured
+/// stop sequence is matched, or the model produces an end-of-generation token (unless biased away
+/// via [`GenerateOptions::ignore_eos`]).
+pub struct GenerationSession<'ctx, 'model> {
+    ctx: &'ctx mut LlamaContext<'model>,
+    model: &'model LlamaModel,
+    batch: LlamaBatch,
+    sampler: LlamaSampler,
+    detokenizer: LlamaDetokenizer<'model>,
+    options: GenerateOptions,
+    n_cur: i32,
+    n_generated: usize,
+    produced: String,
+    finished: bool,
+}
+
+impl<'ctx, 'model> GenerationSession<'ctx, 'model> {
+    /// Start a streaming generation from an already-decoded prompt: `batch` must be the batch the
+    /// prompt was decoded with (so its last logits are the ones to sample from), and `n_cur` the
+    /// next position to generate at.
+    #[must_use]
+    pub fn new(
+        ctx: &'ctx mut LlamaContext<'model>,
+        model: &'model LlamaModel,
+        batch: LlamaBatch,
+        n_cur: i32,
+        options: GenerateOptions,
+    ) -> Self {
+        let sampler = LlamaSampler::chain_simple([
+            LlamaSampler::dist(options.seed),
+            LlamaSampler::greedy(),
+        ]);
+        Self {
+            ctx,
+            model,
+            batch,
+            sampler,
+            detokenizer: LlamaDetokenizer::new(model, Special::Tokenize),
+            options,
+            n_cur,
+            n_generated: 0,
+            produced: String::new(),
+            finished: false,
+        }
+    }
+
+    /// Tokenize `prompt`, decode it, and start streaming generation from it -- the one-call
+    /// entry point for the common case, handling the tokenize/batch/decode boilerplate that
+    /// [`Self::new`] leaves to the caller (still useful when a prompt's already been decoded,
+    /// e.g. to continue generation after a prefix shared across requests).
+    ///
+    /// # Errors
+    /// Returns [`GenerateError::Tokenize`] if `prompt` fails to tokenize, or
+    /// [`GenerateError::Decode`] if decoding the prompt fails.
+    pub fn generate(
+        ctx: &'ctx mut LlamaContext<'model>,
+        model: &'model LlamaModel,
+        prompt: &str,
+        add_bos: AddBos,
+        options: GenerateOptions,
+    ) -> Result<Self, GenerateError> {
+        let tokens = model.str_to_token(prompt, add_bos)?;
+        let n_tokens = tokens.len();
+        let mut batch = LlamaBatch::new(n_tokens.max(1), 1);
+        for (i, token) in tokens.into_iter().enumerate() {
+            let pos = i32::try_from(i).expect("prompt position fits into an i32");
+            batch.add(token, pos, &[0], i == n_tokens - 1)?;
+        }
+        ctx.decode(&mut batch)?;
+
+        let n_cur = i32::try_from(n_tokens).expect("prompt length fits into an i32");
+        Ok(Self::new(ctx, model, batch, n_cur, options))
+    }
+
+    fn matches_stop(&self) -> bool {
+        self.options
+            .stop
+            .iter()
+            .any(|stop| self.produced.ends_with(stop.as_str()))
+    }
+
+    /// Called once `self.finished` has just been set to `true` without a piece already in hand
+    /// (the `max_tokens` and end-of-generation cases): flushes whatever text the detokenizer was
+    /// still holding back -- a buffered partial UTF-8 sequence or a `clean_up_tokenization_spaces`
+    /// tail -- so it isn't silently dropped.
+    fn finish_iteration(&mut self) -> Option<<Self as Iterator>::Item> {
+        let tail = self.detokenizer.finish();
+        if tail.is_empty() {
+            None
+        } else {
+            Some(Ok(tail))
+        }
+    }
+}
+
+impl Iterator for GenerationSession<'_, '_> {
+    type Item = Result<String, GenerateError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        if let Some(max_tokens) = self.options.max_tokens {
+            if self.n_generated >= max_tokens {
+                self.finished = true;
+                return self.finish_iteration();
+            }
+        }
+
+        if !self.options.logit_bias.is_empty() {
+            let idx = self.batch.n_tokens() - 1;
+            if let Ok(logits) = self.ctx.logits_ith_mut(idx) {
+                for (&token, &bias) in &self.options.logit_bias {
+                    if let Ok(i) = usize::try_from(token.value()) {
+                        if let Some(logit) = logits.get_mut(i) {
+                            *logit += bias;
+                        }
+                    }
+                }
+            }
+        }
+
+        let token = self.sampler.sample(self.ctx, self.batch.n_tokens() - 1);
+        self.sampler.accept(token);
+
+        if self.model.is_eog_token(token) {
+            self.finished = true;
+            return self.finish_iteration();
+        }
+
+        let piece = self.detokenizer.push(token);
+        self.produced.push_str(&piece);
+        self.n_generated += 1;
+
+        self.batch.clear();
+        if let Err(e) = self.batch.add(token, self.n_cur, &[0], true) {
+            self.finished = true;
+            return Some(Err(e.into()));
+        }
+        self.n_cur += 1;
+
+        if let Err(e) = self.ctx.decode(&mut self.batch) {
+            self.finished = true;
+            return Some(Err(e.into()));
+        }
+
+        if self.matches_stop() {
+            self.finished = true;
+            let mut piece = piece;
+            piece.push_str(&self.detokenizer.finish());
+            return Some(Ok(piece));
+        }
+
+        Some(Ok(piece))
+    }
+}
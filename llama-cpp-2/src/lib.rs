@@ -0,0 +1,106 @@
+//! Bindings to the llama.cpp library.
+//!
+//! As llama.cpp is a very fast moving target, this crate does not attempt to create a perfectly
+//! safe abstraction over it, but instead provides thin, safe wrappers around the individual
+//! pieces (model, context, batch, sampler, tokens) that can be composed together.
+use std::ffi::NulError;
+use std::num::TryFromIntError;
+
+pub mod context;
+pub mod generate;
+pub mod llama_backend;
+pub mod llama_batch;
+pub mod model;
+pub mod sampling;
+pub mod token;
+pub mod token_type;
+
+/// Failed to load a model.
+#[derive(Debug, thiserror::Error)]
+pub enum LlamaModelLoadError {
+    /// llama.cpp returned a null pointer from `llama_load_model_from_file`.
+    #[error("null model returned from llama.cpp")]
+    NullResult,
+    /// The path to the model could not be converted to a `CString`.
+    #[error("path contained a null byte: {0}")]
+    PathToStrError(#[from] NulError),
+}
+
+/// Failed to create a [`context::LlamaContext`] from a [`model::LlamaModel`].
+#[derive(Debug, thiserror::Error)]
+pub enum LlamaContextLoadError {
+    /// llama.cpp returned a null pointer from `llama_new_context_with_model`.
+    #[error("null context returned from llama.cpp")]
+    NullReturn,
+}
+
+/// Failed to initialize a LoRA adapter.
+#[derive(Debug, thiserror::Error)]
+pub enum LlamaLoraAdapterInitError {
+    /// llama.cpp returned a null pointer from `llama_adapter_lora_init`.
+    #[error("null lora adapter returned from llama.cpp")]
+    NullResult,
+    /// The path to the adapter could not be converted to a `CString`.
+    #[error("path contained a null byte: {0}")]
+    PathToStrError(#[from] NulError),
+}
+
+/// Failed to construct a [`model::LlamaChatMessage`].
+#[derive(Debug, thiserror::Error)]
+pub enum NewLlamaChatMessageError {
+    /// The role contained a null byte.
+    #[error("role contained a null byte: {0}")]
+    RoleToStrError(NulError),
+    /// The content contained a null byte.
+    #[error("content contained a null byte: {0}")]
+    ContentToStrError(NulError),
+}
+
+impl From<NulError> for NewLlamaChatMessageError {
+    fn from(value: NulError) -> Self {
+        // both fields share the same constructor, so attribute to content -- callers that need
+        // to distinguish should construct the variant directly.
+        Self::ContentToStrError(value)
+    }
+}
+
+/// Failed to apply a chat template.
+#[derive(Debug, thiserror::Error)]
+pub enum ApplyChatTemplateError {
+    /// The chat template buffer was too small and the retry also failed.
+    #[error("failed to apply chat template")]
+    FailedToApplyChatTemplate,
+}
+
+/// Failed to look up a chat template on a model.
+#[derive(Debug, thiserror::Error)]
+pub enum ChatTemplateError {
+    /// The model does not have a chat template with the given name.
+    #[error("model has no chat template")]
+    MissingTemplate,
+    /// The template bytes were not valid UTF-8.
+    #[error("template was not valid utf8: {0}")]
+    Utf8Error(#[from] std::string::FromUtf8Error),
+}
+
+/// Failed to convert a string into tokens.
+#[derive(Debug, thiserror::Error)]
+pub enum StringToTokenError {
+    /// The string contained a null byte.
+    #[error("string contained a null byte: {0}")]
+    NulError(#[from] NulError),
+    /// more tokens were returned than fit into an `i32`.
+    #[error("{0}")]
+    TryFromIntError(#[from] TryFromIntError),
+}
+
+/// Failed to convert a token into a string or bytes.
+#[derive(Debug, thiserror::Error)]
+pub enum TokenToStringError {
+    /// llama.cpp's buffer was too small; contains the negated required size.
+    #[error("insufficient buffer space, needed {0}")]
+    InsufficientBufferSpace(i32),
+    /// The bytes returned by llama.cpp were not valid UTF-8.
+    #[error("token bytes were not valid utf8: {0}")]
+    Utf8Error(#[from] std::string::FromUtf8Error),
+}
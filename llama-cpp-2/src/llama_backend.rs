@@ -0,0 +1,31 @@
+//! A safe wrapper around the global `llama.cpp` backend state.
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static BACKEND_INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// The backend must be initialized once per process before any model can be loaded, and freed
+/// once all models and contexts have been dropped.
+#[derive(Debug)]
+pub struct LlamaBackend {}
+
+impl LlamaBackend {
+    /// Initializes the llama.cpp backend.
+    ///
+    /// # Panics
+    /// If the backend was already initialized.
+    pub fn init() -> Self {
+        assert!(
+            !BACKEND_INITIALIZED.swap(true, Ordering::SeqCst),
+            "llama backend already initialized"
+        );
+        unsafe { llama_cpp_sys_2::llama_backend_init() }
+        Self {}
+    }
+}
+
+impl Drop for LlamaBackend {
+    fn drop(&mut self) {
+        unsafe { llama_cpp_sys_2::llama_backend_free() }
+        BACKEND_INITIALIZED.store(false, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,115 @@
+//! A safe wrapper around `llama_batch`.
+use crate::token::LlamaToken;
+
+/// Errors that can occur when adding a token to a [`LlamaBatch`].
+#[derive(Debug, thiserror::Error)]
+pub enum BatchAddError {
+    /// The batch is already at its configured capacity.
+    #[error("batch is full, capacity is {0}")]
+    Full(usize),
+    /// More sequence ids were given for one token than the batch was constructed to support.
+    #[error("{given} seq_ids given, but this batch was constructed with n_seq_max = {n_seq_max}")]
+    TooManySeqIds {
+        /// How many sequence ids were passed to [`LlamaBatch::add`].
+        given: usize,
+        /// The `n_seq_max` the batch was constructed with.
+        n_seq_max: usize,
+    },
+}
+
+/// A batch of tokens to be decoded in a single `llama_decode` call.
+///
+/// Wraps `llama_batch_init`/`llama_batch_free` and tracks how many tokens have been added so far.
+#[derive(Debug)]
+pub struct LlamaBatch {
+    batch: llama_cpp_sys_2::llama_batch,
+    capacity: usize,
+    n_seq_max: usize,
+    n_tokens: i32,
+}
+
+impl LlamaBatch {
+    /// Create a new batch able to hold up to `capacity` tokens, each belonging to at most
+    /// `n_seq_max` sequences.
+    #[must_use]
+    pub fn new(capacity: usize, n_seq_max: i32) -> Self {
+        let batch = unsafe {
+            llama_cpp_sys_2::llama_batch_init(
+                capacity.try_into().expect("capacity fits into an i32"),
+                0,
+                n_seq_max,
+            )
+        };
+        Self {
+            batch,
+            capacity,
+            n_seq_max: usize::try_from(n_seq_max).expect("n_seq_max is non-negative"),
+            n_tokens: 0,
+        }
+    }
+
+    /// The number of tokens currently staged in the batch.
+    #[must_use]
+    pub fn n_tokens(&self) -> i32 {
+        self.n_tokens
+    }
+
+    /// The raw `llama_batch`, for passing to `llama_decode`.
+    pub(crate) fn handle(&self) -> llama_cpp_sys_2::llama_batch {
+        self.batch
+    }
+
+    /// Clear all staged tokens, keeping the underlying allocation.
+    pub fn clear(&mut self) {
+        self.n_tokens = 0;
+        self.batch.n_tokens = 0;
+    }
+
+    /// Stage a token at position `pos`, belonging to `seq_ids`, optionally requesting logits for
+    /// it.
+    ///
+    /// # Errors
+    /// Returns [`BatchAddError::Full`] if the batch is already at capacity, or
+    /// [`BatchAddError::TooManySeqIds`] if `seq_ids` is longer than the `n_seq_max` this batch was
+    /// constructed with.
+    pub fn add(
+        &mut self,
+        token: LlamaToken,
+        pos: llama_cpp_sys_2::llama_pos,
+        seq_ids: &[llama_cpp_sys_2::llama_seq_id],
+        logits: bool,
+    ) -> Result<(), BatchAddError> {
+        let i = self.n_tokens;
+        let offset = usize::try_from(i).expect("n_tokens is non-negative");
+        if offset >= self.capacity {
+            return Err(BatchAddError::Full(self.capacity));
+        }
+        if seq_ids.len() > self.n_seq_max {
+            return Err(BatchAddError::TooManySeqIds {
+                given: seq_ids.len(),
+                n_seq_max: self.n_seq_max,
+            });
+        }
+        unsafe {
+            *self.batch.token.add(offset) = token.0;
+            *self.batch.pos.add(offset) = pos;
+            *self.batch.n_seq_id.add(offset) = seq_ids
+                .len()
+                .try_into()
+                .expect("seq_ids.len() fits into an i32, already checked against n_seq_max");
+            for (j, seq_id) in seq_ids.iter().enumerate() {
+                *(*self.batch.seq_id.add(offset)).add(j) = *seq_id;
+            }
+            *self.batch.logits.add(offset) = i8::from(logits);
+        }
+        self.n_tokens += 1;
+        self.batch.n_tokens = self.n_tokens;
+        Ok(())
+    }
+}
+
+impl Drop for LlamaBatch {
+    fn drop(&mut self) {
+        unsafe { llama_cpp_sys_2::llama_batch_free(self.batch) }
+    }
+}
@@ -100,14 +100,26 @@ impl LlamaModel {
         u32::try_from(n_ctx_train).expect("n_ctx_train fits into an u32")
     }
 
-    /// Get all tokens in the model.
+    /// Get the size of a single embedding vector produced by this model.
+    ///
+    /// # Panics
+    ///
+    /// If the embedding size does not fit into a `u32`. This should be impossible in practice, as
+    /// it mirrors [`Self::n_ctx_train`]'s panic condition.
+    #[must_use]
+    pub fn n_embd(&self) -> u32 {
+        let n_embd = unsafe { llama_cpp_sys_2::llama_model_n_embd(self.model.as_ptr()) };
+        u32::try_from(n_embd).expect("n_embd fits into an u32")
+    }
+
+    /// Get all tokens in the model, rendering special/control tokens verbatim.
     pub fn tokens(
         &self,
         special: Special,
     ) -> impl Iterator<Item = (LlamaToken, Result<String, TokenToStringError>)> + '_ {
-        (0..self.n_vocab())
-            .map(LlamaToken::new)
-            .map(move |llama_token| (llama_token, self.token_to_str(llama_token, special)))
+        (0..self.n_vocab()).map(LlamaToken::new).map(move |llama_token| {
+            (llama_token, self.token_to_str(llama_token, special, true))
+        })
     }
 
     /// Get the beginning of stream token.
@@ -132,15 +144,39 @@ impl LlamaModel {
     }
 
     /// Check if a token represents the end of generation (end of turn, end of sequence, etc.)
+    ///
+    /// This only reflects the token itself; detecting a run of repeated tokens as a stuck
+    /// generation loop is the caller's responsibility, not this method's.
     #[must_use]
     pub fn is_eog_token(&self, token: LlamaToken) -> bool {
-        // Also check our last token to avoid repeats
-        static mut LAST_TOKEN: Option<LlamaToken> = None;
-        unsafe {
-            let is_repeat = LAST_TOKEN == Some(token);
-            LAST_TOKEN = Some(token);
-            is_repeat || token == self.token_eos() || llama_cpp_sys_2::llama_token_is_eog(self.vocab_ptr(), token.0)
+        unsafe { llama_cpp_sys_2::llama_token_is_eog(self.vocab_ptr(), token.0) }
+    }
+
+    /// Known end-of-turn token text used by chat-tuned models whose GGUF KV metadata doesn't mark
+    /// them as an EOG token, mirroring llama.cpp's auto-detection in `llama_vocab::impl::load`.
+    const AUTO_DETECTED_EOG_TOKEN_TEXT: &[&str] =
+        &["<|eot_id|>", "<|im_end|>", "<end_of_turn>", "<|end_of_turn|>"];
+
+    /// All tokens that represent the end of generation: those llama.cpp natively considers EOG
+    /// (EOS, EOT, ...), plus any of the well-known chat end-of-turn tokens that exist in this
+    /// model's vocabulary but whose GGUF metadata didn't flag them, following llama.cpp's
+    /// auto-detection of missing EOT tokens for models like Llama 3 and codegemma.
+    #[must_use]
+    pub fn eog_tokens(&self) -> Vec<LlamaToken> {
+        let mut tokens: Vec<LlamaToken> = (0..self.n_vocab())
+            .map(LlamaToken::new)
+            .filter(|&token| self.is_eog_token(token))
+            .collect();
+
+        for (token, text) in self.tokens(Special::Tokenize) {
+            let Ok(text) = text else { continue };
+            if Self::AUTO_DETECTED_EOG_TOKEN_TEXT.contains(&text.as_str()) && !tokens.contains(&token)
+            {
+                tokens.push(token);
+            }
         }
+
+        tokens
     }
 
     /// Get the decoder start token.
@@ -160,13 +196,19 @@ impl LlamaModel {
         &self,
         token: LlamaToken,
         special: Special,
+        render_special: bool,
     ) -> Result<String, TokenToStringError> {
-        let bytes = self.token_to_bytes(token, special)?;
+        let bytes = self.token_to_bytes(token, special, render_special)?;
         Ok(String::from_utf8(bytes)?)
     }
 
     /// Convert single token to bytes.
     ///
+    /// `render_special` controls whether special/control tokens (e.g. `<|eot_id|>`, `<s>`) are
+    /// rendered as their literal text or suppressed entirely, mirroring llama.cpp's option to
+    /// render special/control tokens during detokenization. This is independent of `special`,
+    /// which only governs how the token was produced by the tokenizer.
+    ///
     /// # Errors
     /// See [`TokenToStringError`] for more information.
     ///
@@ -178,12 +220,12 @@ impl LlamaModel {
         &self,
         token: LlamaToken,
         special: Special,
+        render_special: bool,
     ) -> Result<Vec<u8>, TokenToStringError> {
-        // Only filter true EOS tokens
-        if token == self.token_eos() && self.is_eog_token(token) {
-            return Ok(b"\n".to_vec()); // Convert EOS to newline 
+        if !render_special && self.is_special_or_control_token(token) {
+            return Ok(Vec::new());
         }
-        
+
         match self.token_to_bytes_with_size(token, 8, special, None) {
             Err(TokenToStringError::InsufficientBufferSpace(i)) => self.token_to_bytes_with_size(
                 token,
@@ -194,4 +236,68 @@ impl LlamaModel {
             x => x,
         }
     }
+
+    /// Whether `token` is a special or control token (e.g. `<s>`, `<|eot_id|>`), as opposed to a
+    /// normal piece of text.
+    pub(crate) fn is_special_or_control_token(&self, token: LlamaToken) -> bool {
+        let attr = unsafe { llama_cpp_sys_2::llama_token_get_attr(self.vocab_ptr(), token.0) };
+        attr & (LlamaTokenAttr::Control as llama_cpp_sys_2::llama_token_attr) != 0
+            || attr & (LlamaTokenAttr::Unknown as llama_cpp_sys_2::llama_token_attr) != 0
+    }
+
+    /// Tokenize `str`, optionally prepending the beginning-of-stream token, and allowing special
+    /// tokens in the input (e.g. `<|eot_id|>`) to be parsed as their single token rather than as
+    /// plain text.
+    ///
+    /// # Errors
+    /// Returns [`StringToTokenError`] if `str` contains a null byte, or if converting between the
+    /// C and Rust integer types involved overflows.
+    pub fn str_to_token(&self, str: &str, add_bos: AddBos) -> Result<Vec<LlamaToken>, StringToTokenError> {
+        let add_bos = matches!(add_bos, AddBos::Always);
+
+        let c_string = CString::new(str)?;
+        let text_len = c_int::try_from(c_string.as_bytes().len())?;
+
+        let tokens_estimation = std::cmp::max(8, (str.len() / 2) + usize::from(add_bos));
+        let mut buffer = Vec::with_capacity(tokens_estimation);
+        let buffer_capacity = c_int::try_from(buffer.capacity())?;
+
+        let size = unsafe {
+            llama_cpp_sys_2::llama_tokenize(
+                self.vocab_ptr(),
+                c_string.as_ptr(),
+                text_len,
+                buffer.as_mut_ptr(),
+                buffer_capacity,
+                add_bos,
+                true,
+            )
+        };
+
+        // A negative return means the buffer was too small; it holds the number of tokens
+        // actually needed, negated. Grow the buffer to fit and retry once.
+        let size = if size.is_negative() {
+            buffer.reserve_exact(usize::try_from(-size)?);
+            unsafe {
+                llama_cpp_sys_2::llama_tokenize(
+                    self.vocab_ptr(),
+                    c_string.as_ptr(),
+                    text_len,
+                    buffer.as_mut_ptr(),
+                    -size,
+                    add_bos,
+                    true,
+                )
+            }
+        } else {
+            size
+        };
+
+        let size = usize::try_from(size)?;
+        // Safety: `llama_tokenize` just initialized exactly `size` elements of `buffer`, and
+        // `size` is no more than `buffer`'s capacity from either attempt above.
+        unsafe { buffer.set_len(size) };
+
+        Ok(buffer.into_iter().map(LlamaToken).collect())
+    }
 }
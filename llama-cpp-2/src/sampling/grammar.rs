@@ -0,0 +1,56 @@
+//! GBNF grammar-constrained sampling.
+use std::ffi::CString;
+
+use crate::model::LlamaModel;
+use crate::sampling::json_schema::json_schema_to_gbnf;
+use crate::sampling::LlamaSampler;
+
+/// Failed to build a grammar sampler.
+#[derive(Debug, thiserror::Error)]
+pub enum GrammarInitError {
+    /// The grammar source or root rule contained a null byte.
+    #[error("grammar source contained a null byte: {0}")]
+    NulError(#[from] std::ffi::NulError),
+    /// llama.cpp rejected the grammar, most likely because it failed to parse.
+    #[error("llama.cpp failed to initialize the grammar, check the GBNF source is valid")]
+    InvalidGrammar,
+}
+
+impl LlamaSampler {
+    /// Build a sampler that masks logits to only tokens consistent with a GBNF grammar, advancing
+    /// the grammar's internal parse stack as tokens are [`LlamaSampler::accept`]ed so later steps
+    /// only see what the partial parse still allows. Compose it before `dist`/`greedy` in
+    /// [`LlamaSampler::chain_simple`].
+    ///
+    /// # Errors
+    /// Returns [`GrammarInitError`] if `gbnf_source` or `root_rule` contain a null byte, or if
+    /// llama.cpp fails to parse the grammar.
+    pub fn grammar(
+        model: &LlamaModel,
+        gbnf_source: &str,
+        root_rule: &str,
+    ) -> Result<Self, GrammarInitError> {
+        let gbnf_source = CString::new(gbnf_source)?;
+        let root_rule = CString::new(root_rule)?;
+        let sampler = unsafe {
+            llama_cpp_sys_2::llama_sampler_init_grammar(
+                model.vocab_ptr(),
+                gbnf_source.as_ptr(),
+                root_rule.as_ptr(),
+            )
+        };
+        Ok(Self {
+            sampler: std::ptr::NonNull::new(sampler).ok_or(GrammarInitError::InvalidGrammar)?,
+        })
+    }
+
+    /// Convenience constructor that converts a JSON schema to GBNF before building the grammar
+    /// sampler, rooted at `root`.
+    ///
+    /// # Errors
+    /// Returns [`GrammarInitError`] if the generated grammar is rejected by llama.cpp.
+    pub fn json_schema(model: &LlamaModel, schema: &str) -> Result<Self, GrammarInitError> {
+        let gbnf = json_schema_to_gbnf(schema);
+        Self::grammar(model, &gbnf, "root")
+    }
+}
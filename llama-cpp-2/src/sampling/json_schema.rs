@@ -0,0 +1,395 @@
+//! A minimal JSON parser and JSON-Schema-to-GBNF translator, used by
+//! [`LlamaSampler::json_schema`](super::LlamaSampler::json_schema).
+//!
+//! This only supports the subset of JSON Schema needed to constrain structured generation:
+//! `type` (`object`, `array`, `string`, `number`/`integer`, `boolean`, `null`), `properties` +
+//! `required` on objects, `items` on arrays, and `enum`. Anything outside that subset falls back
+//! to the permissive `json-value` rule, so unsupported schemas still produce valid GBNF rather
+//! than failing to parse.
+use std::collections::BTreeMap;
+
+/// A parsed JSON value, just rich enough to read back a JSON Schema document.
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Keeps insertion order, since `properties` order is meaningful for matching `required`.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a JSON document into a [`Json`] tree. Returns `None` on any parse error -- callers fall
+/// back to the permissive grammar rather than surfacing a parse error, since an invalid schema
+/// should still let the model produce *some* valid JSON.
+fn parse(input: &str) -> Option<Json> {
+    let mut chars = input.char_indices().peekable();
+    let value = parse_value(input, &mut chars)?;
+    Some(value)
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::CharIndices<'a>>;
+
+fn skip_ws(chars: &mut Chars) {
+    while matches!(chars.peek(), Some((_, c)) if c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+fn parse_value(input: &str, chars: &mut Chars) -> Option<Json> {
+    skip_ws(chars);
+    match chars.peek()? {
+        (_, '{') => parse_object(input, chars),
+        (_, '[') => parse_array(input, chars),
+        (_, '"') => parse_string(chars).map(Json::String),
+        (_, 't') => parse_literal(input, chars, "true").map(|()| Json::Bool(true)),
+        (_, 'f') => parse_literal(input, chars, "false").map(|()| Json::Bool(false)),
+        (_, 'n') => parse_literal(input, chars, "null").map(|()| Json::Null),
+        (_, c) if c == '-' || c.is_ascii_digit() => parse_number(input, chars),
+        _ => None,
+    }
+}
+
+fn parse_literal(input: &str, chars: &mut Chars, literal: &str) -> Option<()> {
+    let (start, _) = *chars.peek()?;
+    let end = start + literal.len();
+    if input.get(start..end) == Some(literal) {
+        for _ in 0..literal.chars().count() {
+            chars.next();
+        }
+        Some(())
+    } else {
+        None
+    }
+}
+
+fn parse_number(input: &str, chars: &mut Chars) -> Option<Json> {
+    let (start, _) = *chars.peek()?;
+    let mut end = start;
+    while matches!(chars.peek(), Some((_, c)) if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+    {
+        end = chars.next()?.0 + 1;
+    }
+    input.get(start..end)?.parse().ok().map(Json::Number)
+}
+
+fn parse_string(chars: &mut Chars) -> Option<String> {
+    chars.next(); // opening quote
+    let mut out = String::new();
+    loop {
+        let (_, c) = chars.next()?;
+        match c {
+            '"' => return Some(out),
+            '\\' => {
+                let (_, escaped) = chars.next()?;
+                out.push(match escaped {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    other => other,
+                });
+            }
+            c => out.push(c),
+        }
+    }
+}
+
+fn parse_array(input: &str, chars: &mut Chars) -> Option<Json> {
+    chars.next(); // '['
+    let mut items = Vec::new();
+    skip_ws(chars);
+    if matches!(chars.peek(), Some((_, ']'))) {
+        chars.next();
+        return Some(Json::Array(items));
+    }
+    loop {
+        items.push(parse_value(input, chars)?);
+        skip_ws(chars);
+        match chars.next()? {
+            (_, ',') => continue,
+            (_, ']') => return Some(Json::Array(items)),
+            _ => return None,
+        }
+    }
+}
+
+fn parse_object(input: &str, chars: &mut Chars) -> Option<Json> {
+    chars.next(); // '{'
+    let mut entries = Vec::new();
+    skip_ws(chars);
+    if matches!(chars.peek(), Some((_, '}'))) {
+        chars.next();
+        return Some(Json::Object(entries));
+    }
+    loop {
+        skip_ws(chars);
+        let key = parse_string(chars)?;
+        skip_ws(chars);
+        if chars.next()?.1 != ':' {
+            return None;
+        }
+        let value = parse_value(input, chars)?;
+        entries.push((key, value));
+        skip_ws(chars);
+        match chars.next()? {
+            (_, ',') => continue,
+            (_, '}') => return Some(Json::Object(entries)),
+            _ => return None,
+        }
+    }
+}
+
+/// GBNF source for the primitive rules every generated grammar can rely on.
+const PRIMITIVES: &str = r#"
+ws ::= [ \t\n]*
+json-value ::= object | array | string | number | boolean | null
+object ::= "{" ws (string ws ":" ws json-value (ws "," ws string ws ":" ws json-value)*)? ws "}"
+array ::= "[" ws (json-value (ws "," ws json-value)*)? ws "]"
+string ::= "\"" ([^"\\] | "\\" .)* "\""
+number ::= "-"? [0-9]+ ("." [0-9]+)?
+boolean ::= "true" | "false"
+null ::= "null"
+"#;
+
+/// Convert a (subset of) JSON Schema to a GBNF grammar rooted at `root`.
+#[must_use]
+pub fn json_schema_to_gbnf(schema: &str) -> String {
+    let mut rules: BTreeMap<String, String> = BTreeMap::new();
+    let root_rule = match parse(schema) {
+        Some(schema) => schema_to_rule(&schema, &mut rules, "root"),
+        // Not parseable as JSON at all: fall back to permitting any JSON value.
+        None => "json-value".to_string(),
+    };
+
+    // `schema_to_rule` either defines a rule literally named `rule_name` ("root" here, already
+    // covering the `root ::=` line) or returns a bare reference to one of the shared primitive
+    // rules -- only the latter still needs a `root ::=` line written out.
+    let mut out = if rules.contains_key("root") {
+        String::new()
+    } else {
+        format!("root ::= {root_rule}\n")
+    };
+    for (_, body) in rules {
+        out.push_str(&body);
+        out.push('\n');
+    }
+    out.push_str(PRIMITIVES);
+    out
+}
+
+/// Translate one schema node into a GBNF rule reference, defining any named rules it needs (e.g.
+/// one per object) into `rules`, keyed by rule name to keep output deterministic.
+fn schema_to_rule(schema: &Json, rules: &mut BTreeMap<String, String>, rule_name: &str) -> String {
+    if let Some(values) = schema.get("enum").and_then(Json::as_array) {
+        let alternatives: Vec<String> = values
+            .iter()
+            .filter_map(|v| match v {
+                Json::String(s) => Some(gbnf_string_literal(s)),
+                Json::Number(n) => Some(gbnf_string_literal(&n.to_string())),
+                Json::Bool(b) => Some(gbnf_string_literal(&b.to_string())),
+                Json::Null => Some("\"null\"".to_string()),
+                Json::Array(_) | Json::Object(_) => None,
+            })
+            .collect();
+        if !alternatives.is_empty() {
+            let body = format!("{rule_name} ::= {}", alternatives.join(" | "));
+            rules.insert(rule_name.to_string(), body);
+            return rule_name.to_string();
+        }
+    }
+
+    match schema.get("type").and_then(Json::as_str) {
+        Some("string") => "string".to_string(),
+        Some("number" | "integer") => "number".to_string(),
+        Some("boolean") => "boolean".to_string(),
+        Some("null") => "null".to_string(),
+        Some("array") => {
+            let item_rule = schema
+                .get("items")
+                .map(|items| schema_to_rule(items, rules, &format!("{rule_name}-item")))
+                .unwrap_or_else(|| "json-value".to_string());
+            let body = format!(
+                "{rule_name} ::= \"[\" ws ({item_rule} (ws \",\" ws {item_rule})*)? ws \"]\""
+            );
+            rules.insert(rule_name.to_string(), body);
+            rule_name.to_string()
+        }
+        Some("object") | None if schema.get("properties").is_some() => {
+            object_schema_to_rule(schema, rules, rule_name)
+        }
+        _ => "json-value".to_string(),
+    }
+}
+
+/// Build the GBNF rule for an object schema's member list, in schema-declared property order.
+/// Required properties must be emitted; optional ones may be skipped, so the grammar allows any
+/// subset of optional properties to be present (still in declared order) rather than either
+/// dropping them from the grammar entirely or requiring all of them.
+///
+/// This is encoded as a chain of two rules per property, built back-to-front: `{rule_name}-from-i`
+/// is "nothing emitted yet, starting at property `i`" and `{rule_name}-after-i` is "something
+/// already emitted, continuing from property `i`" (so it always needs a leading `,`). A required
+/// property must appear in both; an optional property makes the whole continuation optional,
+/// falling through to the next property in the same mode if skipped.
+fn object_schema_to_rule(schema: &Json, rules: &mut BTreeMap<String, String>, rule_name: &str) -> String {
+    let Some(Json::Object(properties)) = schema.get("properties") else {
+        return "object".to_string();
+    };
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Json::as_array)
+        .map(|values| values.iter().filter_map(Json::as_str).collect())
+        .unwrap_or_default();
+
+    let kvs: Vec<(bool, String)> = properties
+        .iter()
+        .enumerate()
+        .map(|(i, (name, prop_schema))| {
+            let prop_rule = schema_to_rule(prop_schema, rules, &format!("{rule_name}-{i}"));
+            let kv = format!("{} ws \":\" ws {prop_rule}", gbnf_string_literal(name));
+            (required.contains(&name.as_str()), kv)
+        })
+        .collect();
+
+    let n = kvs.len();
+    rules.insert(
+        format!("{rule_name}-from-{n}"),
+        format!("{rule_name}-from-{n} ::= \"\""),
+    );
+    rules.insert(
+        format!("{rule_name}-after-{n}"),
+        format!("{rule_name}-after-{n} ::= \"\""),
+    );
+    for (i, (required, kv)) in kvs.iter().enumerate().rev() {
+        let from_next = format!("{rule_name}-from-{}", i + 1);
+        let after_next = format!("{rule_name}-after-{}", i + 1);
+        let emit = format!("{kv} {after_next}");
+        let from_body = if *required {
+            emit
+        } else {
+            format!("({emit}) | {from_next}")
+        };
+        let after_body = if *required {
+            format!("ws \",\" ws {kv} {after_next}")
+        } else {
+            format!("(ws \",\" ws {kv} {after_next})?")
+        };
+        rules.insert(
+            format!("{rule_name}-from-{i}"),
+            format!("{rule_name}-from-{i} ::= {from_body}"),
+        );
+        rules.insert(
+            format!("{rule_name}-after-{i}"),
+            format!("{rule_name}-after-{i} ::= {after_body}"),
+        );
+    }
+
+    let body = format!("{rule_name} ::= \"{{\" ws {rule_name}-from-0 ws \"}}\"");
+    rules.insert(rule_name.to_string(), body);
+    rule_name.to_string()
+}
+
+/// Render a Rust string as a GBNF string literal.
+fn gbnf_string_literal(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unparseable_schema_falls_back_to_json_value() {
+        let gbnf = json_schema_to_gbnf("not json");
+        assert!(gbnf.starts_with("root ::= json-value"));
+    }
+
+    #[test]
+    fn primitive_type_schema_is_constrained() {
+        let gbnf = json_schema_to_gbnf(r#"{"type": "string"}"#);
+        assert!(gbnf.starts_with("root ::= string"));
+    }
+
+    #[test]
+    fn enum_schema_generates_alternatives() {
+        let gbnf = json_schema_to_gbnf(r#"{"enum": ["a", "b"]}"#);
+        assert!(gbnf.contains(r#"root ::= "a" | "b""#));
+    }
+
+    #[test]
+    fn object_schema_with_required_properties_is_constrained() {
+        let gbnf = json_schema_to_gbnf(
+            r#"{"type": "object", "properties": {"name": {"type": "string"}, "age": {"type": "integer"}}, "required": ["name", "age"]}"#,
+        );
+        assert!(gbnf.contains(r#""name" ws ":" ws string"#));
+        assert!(gbnf.contains(r#""age" ws ":" ws number"#));
+        assert!(!gbnf.starts_with("root ::= json-value"));
+    }
+
+    #[test]
+    fn optional_properties_are_constrained_but_skippable() {
+        let gbnf = json_schema_to_gbnf(
+            r#"{"type": "object", "properties": {"name": {"type": "string"}, "nickname": {"type": "string"}}, "required": ["name"]}"#,
+        );
+        // Both properties are still schema-constrained...
+        assert!(gbnf.contains(r#""name" ws ":" ws string"#));
+        assert!(gbnf.contains(r#""nickname" ws ":" ws string"#));
+        // ...but the optional one's continuation is wrapped to make it skippable, unlike the
+        // required one's.
+        assert!(gbnf.contains(r#"(ws "," ws "nickname""#));
+        assert!(!gbnf.starts_with("root ::= json-value"));
+    }
+
+    #[test]
+    fn object_schema_with_no_required_properties_allows_empty_object() {
+        let gbnf = json_schema_to_gbnf(
+            r#"{"type": "object", "properties": {"name": {"type": "string"}}}"#,
+        );
+        assert!(gbnf.contains("root-from-1 ::= \"\""));
+    }
+
+    #[test]
+    fn object_schema_with_no_properties_is_well_formed() {
+        // No `object-members` rule is ever referenced, so this must not leave an undefined rule
+        // behind for llama.cpp's grammar parser to reject.
+        let gbnf = json_schema_to_gbnf(r#"{"type": "object", "properties": {}}"#);
+        assert!(!gbnf.contains("object-members"));
+        assert!(gbnf.contains("root-from-0 ::= \"\""));
+    }
+}
@@ -0,0 +1,74 @@
+//! A safe wrapper around `llama_sampler`.
+use std::ptr::NonNull;
+
+use crate::context::LlamaContext;
+use crate::token::LlamaToken;
+
+pub mod grammar;
+pub mod json_schema;
+pub mod speculative;
+
+/// A chain of samplers applied in order to pick the next token.
+#[derive(Debug)]
+pub struct LlamaSampler {
+    pub(crate) sampler: NonNull<llama_cpp_sys_2::llama_sampler>,
+}
+
+unsafe impl Send for LlamaSampler {}
+
+impl LlamaSampler {
+    /// Build a sampler chain out of individually constructed samplers, applied in order.
+    #[must_use]
+    pub fn chain_simple(samplers: impl IntoIterator<Item = Self>) -> Self {
+        let chain = unsafe {
+            llama_cpp_sys_2::llama_sampler_chain_init(llama_cpp_sys_2::llama_sampler_chain_default_params())
+        };
+        for sampler in samplers {
+            unsafe { llama_cpp_sys_2::llama_sampler_chain_add(chain, sampler.sampler.as_ptr()) }
+            // Ownership of the inner pointer has moved into the chain.
+            std::mem::forget(sampler);
+        }
+        Self {
+            sampler: NonNull::new(chain).expect("llama_sampler_chain_init returned null"),
+        }
+    }
+
+    /// A sampler that picks the token with the highest probability.
+    #[must_use]
+    pub fn greedy() -> Self {
+        let sampler = unsafe { llama_cpp_sys_2::llama_sampler_init_greedy() };
+        Self {
+            sampler: NonNull::new(sampler).expect("llama_sampler_init_greedy returned null"),
+        }
+    }
+
+    /// A sampler that samples from the full probability distribution.
+    #[must_use]
+    pub fn dist(seed: u32) -> Self {
+        let sampler = unsafe { llama_cpp_sys_2::llama_sampler_init_dist(seed) };
+        Self {
+            sampler: NonNull::new(sampler).expect("llama_sampler_init_dist returned null"),
+        }
+    }
+
+    /// Sample the next token given the logits at output index `idx` of the last decode.
+    #[must_use]
+    pub fn sample(&self, ctx: &LlamaContext, idx: i32) -> LlamaToken {
+        let token = unsafe {
+            llama_cpp_sys_2::llama_sampler_sample(self.sampler.as_ptr(), ctx.context.as_ptr(), idx)
+        };
+        LlamaToken(token)
+    }
+
+    /// Inform the sampler chain that `token` was accepted, so stateful samplers (e.g.
+    /// repetition penalties or a grammar) can advance.
+    pub fn accept(&mut self, token: LlamaToken) {
+        unsafe { llama_cpp_sys_2::llama_sampler_accept(self.sampler.as_ptr(), token.0) }
+    }
+}
+
+impl Drop for LlamaSampler {
+    fn drop(&mut self) {
+        unsafe { llama_cpp_sys_2::llama_sampler_free(self.sampler.as_ptr()) }
+    }
+}
@@ -0,0 +1,230 @@
+//! Tree-based speculative decoding, following llama.cpp's `speculative` example: a small draft
+//! model proposes a tree of candidate continuations (possibly several children per node), the
+//! target model verifies the whole tree in a single
+//! [`LlamaContext::decode`](crate::context::LlamaContext::decode) call, and the longest accepted
+//! root-to-node path is returned to the caller.
+use crate::context::LlamaContext;
+use crate::llama_batch::{BatchAddError, LlamaBatch};
+use crate::sampling::LlamaSampler;
+use crate::token::LlamaToken;
+
+/// A single node of the draft tree: a token drafted at a given position, on a given sequence, and
+/// the index of its parent node (`None` for a root).
+#[derive(Debug, Clone, Copy)]
+pub struct DraftNode {
+    /// The token the draft model proposed.
+    pub token: LlamaToken,
+    /// The position this token would occupy if accepted.
+    pub pos: llama_cpp_sys_2::llama_pos,
+    /// The sequence id this branch of the tree is decoded on, so sibling branches don't
+    /// interfere with each other's KV cache.
+    pub seq_id: llama_cpp_sys_2::llama_seq_id,
+    /// The index, into [`DraftTree::nodes`], of this node's parent, or `None` for a root.
+    parent: Option<usize>,
+}
+
+/// A tree of drafted candidate continuations. Unlike a flat chain, a node may have more than one
+/// child, letting the draft model propose several alternative continuations at the same depth;
+/// [`SpeculativeSampler::accept_draft`] picks whichever child actually matches the target model's
+/// verified sample at each depth.
+#[derive(Debug, Default)]
+pub struct DraftTree {
+    nodes: Vec<DraftNode>,
+}
+
+impl DraftTree {
+    /// Create an empty tree.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All nodes in the tree, in the order they were added.
+    #[must_use]
+    pub fn nodes(&self) -> &[DraftNode] {
+        &self.nodes
+    }
+
+    /// The indices of the children of `parent` (`None` for the roots), in the order they were
+    /// added.
+    pub fn children(&self, parent: Option<usize>) -> impl Iterator<Item = usize> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(move |(_, node)| node.parent == parent)
+            .map(|(i, _)| i)
+    }
+
+    fn push(
+        &mut self,
+        parent: Option<usize>,
+        token: LlamaToken,
+        pos: llama_cpp_sys_2::llama_pos,
+        seq_id: llama_cpp_sys_2::llama_seq_id,
+    ) -> usize {
+        self.nodes.push(DraftNode {
+            token,
+            pos,
+            seq_id,
+            parent,
+        });
+        self.nodes.len() - 1
+    }
+}
+
+/// Drives tree-based speculative decoding between a small draft model and a target model.
+///
+/// The draft model proposes a [`DraftTree`] of candidate continuations via
+/// [`Self::propose_children`] -- possibly several children per node -- which are packed into a
+/// single [`LlamaBatch`] (one `seq_id` per branch) and verified by one target-model
+/// [`LlamaContext::decode`] call. [`Self::accept_draft`] then walks the tree root-to-leaf,
+/// accepting the longest path whose nodes all match the target model's verified samples.
+#[derive(Debug)]
+pub struct SpeculativeSampler {
+    n_draft: usize,
+    n_accepted: usize,
+    /// Total tokens proposed across all rounds. Incremented once per drafted node, only after
+    /// the node was successfully staged in the batch, and counted separately from `n_accepted`,
+    /// which only counts drafted tokens that the target model accepted.
+    n_drafted: usize,
+}
+
+impl SpeculativeSampler {
+    /// Create a new speculative sampler that drafts up to `n_draft` tokens per round.
+    #[must_use]
+    pub fn new(n_draft: usize) -> Self {
+        Self {
+            n_draft,
+            n_accepted: 0,
+            n_drafted: 0,
+        }
+    }
+
+    /// How many drafted tokens have been accepted by the target model so far.
+    #[must_use]
+    pub fn n_accepted(&self) -> usize {
+        self.n_accepted
+    }
+
+    /// How many tokens have been drafted so far, across all rounds.
+    #[must_use]
+    pub fn n_drafted(&self) -> usize {
+        self.n_drafted
+    }
+
+    /// The maximum number of tokens drafted per round.
+    #[must_use]
+    pub fn n_draft(&self) -> usize {
+        self.n_draft
+    }
+
+    /// Propose children of `parent` (`None` to propose roots): one per sampler in
+    /// `draft_samplers`, each independently sampling the next token from the draft model's logits
+    /// at `idx` (so different samplers, e.g. with different seeds or top-k, yield different
+    /// sibling branches) and staging it into `tree_batch` on its own `seq_id`, starting at
+    /// `seq_id_base`. Proposing stops early, without an error, once `tree` holds `n_draft` nodes.
+    ///
+    /// # Errors
+    /// Returns an error if staging a drafted token in the batch fails, e.g. because the batch is
+    /// full.
+    pub fn propose_children(
+        &mut self,
+        tree: &mut DraftTree,
+        parent: Option<usize>,
+        pos: llama_cpp_sys_2::llama_pos,
+        seq_id_base: llama_cpp_sys_2::llama_seq_id,
+        draft_samplers: &mut [LlamaSampler],
+        draft_ctx: &LlamaContext,
+        idx: i32,
+        tree_batch: &mut LlamaBatch,
+    ) -> Result<Vec<usize>, BatchAddError> {
+        let mut children = Vec::with_capacity(draft_samplers.len());
+        for (branch, draft_sampler) in draft_samplers.iter_mut().enumerate() {
+            if tree.nodes().len() >= self.n_draft {
+                break;
+            }
+            let seq_id = seq_id_base + llama_cpp_sys_2::llama_seq_id::try_from(branch).unwrap();
+            let token = draft_sampler.sample(draft_ctx, idx);
+            draft_sampler.accept(token);
+            tree_batch.add(token, pos, &[seq_id], true)?;
+            self.n_drafted += 1;
+            children.push(tree.push(parent, token, pos, seq_id));
+        }
+        Ok(children)
+    }
+
+    /// Walk the drafted tree root-to-leaf against the target model's verified samples
+    /// (`target_samples[i]` is the token the target model actually sampled when verifying
+    /// `tree.nodes()[i]`), accepting the longest path whose nodes all match. Where several
+    /// children at a depth match the target's sample, the one leading to the longest accepted
+    /// path is taken (the first, in `DraftTree::children` order, on a tie); if none of the
+    /// children at a depth match, acceptance stops there and the rest of the tree is rejected.
+    ///
+    /// # Panics
+    /// Panics if `target_samples` is shorter than `tree.nodes()`.
+    pub fn accept_draft(&mut self, tree: &DraftTree, target_samples: &[LlamaToken]) -> Vec<LlamaToken> {
+        fn longest_match(tree: &DraftTree, target_samples: &[LlamaToken], parent: Option<usize>) -> Vec<LlamaToken> {
+            tree.children(parent)
+                .filter(|&child| tree.nodes()[child].token == target_samples[child])
+                .map(|child| {
+                    let mut path = vec![tree.nodes()[child].token];
+                    path.extend(longest_match(tree, target_samples, Some(child)));
+                    path
+                })
+                .fold(Vec::new(), |best, path| if path.len() > best.len() { path } else { best })
+        }
+        let accepted = longest_match(tree, target_samples, None);
+        self.n_accepted += accepted.len();
+        accepted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tok(id: i32) -> LlamaToken {
+        LlamaToken::new(id)
+    }
+
+    #[test]
+    fn accept_draft_picks_matching_sibling_and_descends() {
+        let mut tree = DraftTree::new();
+        // Root has two candidate children: 10 (wrong) and 11 (right).
+        let root_wrong = tree.push(None, tok(10), 0, 1);
+        let root_right = tree.push(None, tok(11), 0, 2);
+        // Only the accepted root's subtree matters; give each root a child.
+        let child_of_wrong = tree.push(Some(root_wrong), tok(20), 1, 1);
+        let child_of_right = tree.push(Some(root_right), tok(21), 1, 2);
+
+        // Target samples, indexed by node index: only the "right" branch matches at every depth.
+        let mut target_samples = vec![tok(-1); tree.nodes().len()];
+        target_samples[root_wrong] = tok(999); // mismatch
+        target_samples[root_right] = tok(11); // match
+        target_samples[child_of_wrong] = tok(999); // irrelevant, not reached
+        target_samples[child_of_right] = tok(21); // match
+
+        let mut sampler = SpeculativeSampler::new(4);
+        let accepted = sampler.accept_draft(&tree, &target_samples);
+
+        assert_eq!(accepted, vec![tok(11), tok(21)]);
+        assert_eq!(sampler.n_accepted(), 2);
+    }
+
+    #[test]
+    fn accept_draft_stops_at_first_mismatch() {
+        let mut tree = DraftTree::new();
+        let root = tree.push(None, tok(1), 0, 1);
+        let child = tree.push(Some(root), tok(2), 1, 1);
+
+        let mut target_samples = vec![tok(-1); tree.nodes().len()];
+        target_samples[root] = tok(1); // match
+        target_samples[child] = tok(999); // mismatch
+
+        let mut sampler = SpeculativeSampler::new(2);
+        let accepted = sampler.accept_draft(&tree, &target_samples);
+
+        assert_eq!(accepted, vec![tok(1)]);
+        assert_eq!(sampler.n_accepted(), 1);
+    }
+}
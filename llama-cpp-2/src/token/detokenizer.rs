@@ -0,0 +1,282 @@
+//! A first-class detokenizer mirroring llama.cpp's `llama_detokenize`.
+use crate::model::{LlamaModel, Special};
+use crate::token::LlamaToken;
+
+/// Converts tokens back into text, matching the behaviour of llama.cpp's `llama_detokenize`:
+/// special/control tokens are rendered as standalone pieces with no surrounding space, a single
+/// leading space is stripped from the piece of the first token pushed (unless that token is a
+/// special piece), and invalid byte sequences are replaced with `\u{FFFD}` rather than erroring.
+///
+/// Use [`Self::detokenize`] to convert a whole slice of tokens at once, or feed tokens one at a
+/// time with [`Self::push`] for streaming generation; the streaming path buffers any trailing
+/// partial UTF-8 sequence so callers only ever see complete characters.
+#[derive(Debug)]
+pub struct LlamaDetokenizer<'model> {
+    model: &'model LlamaModel,
+    special: Special,
+    /// Whether special/control tokens (e.g. `<|eot_id|>`, `<s>`) are rendered as their literal
+    /// text, as opposed to being suppressed from the output entirely. Defaults to `true`; chat
+    /// UIs that want to hide control markers from normal output should set this to `false`.
+    render_special: bool,
+    /// Whether llama.cpp's convention of stripping a single leading space should still be applied
+    /// to the next piece; set only until the first token has been pushed.
+    remove_leading_space: bool,
+    /// Whether runs of tokenizer-inserted spaces around punctuation should be cleaned up, as
+    /// `clean_up_tokenization_spaces` does in Hugging Face tokenizers.
+    clean_up_tokenization_spaces: bool,
+    /// Substitute the EOS token for a newline. Off by default -- callers that want the old
+    /// `token_to_bytes` behaviour must opt in explicitly.
+    eos_to_newline: bool,
+    /// Bytes of a multi-byte UTF-8 sequence that hasn't been completed by a token yet.
+    pending: Vec<u8>,
+    /// Trailing characters already decoded but held back from the last [`Self::push`] return,
+    /// in case the next token's text completes a `clean_up_tokenization_spaces` pattern that
+    /// spans the boundary between the two pieces (e.g. a token that is just `" "` followed by a
+    /// token that starts with `"."`).
+    clean_up_tail: String,
+}
+
+impl<'model> LlamaDetokenizer<'model> {
+    /// Create a detokenizer for `model`. Special/control tokens are rendered verbatim by default;
+    /// use [`Self::with_render_special`] to suppress them instead.
+    #[must_use]
+    pub fn new(model: &'model LlamaModel, special: Special) -> Self {
+        Self {
+            model,
+            special,
+            render_special: true,
+            remove_leading_space: true,
+            clean_up_tokenization_spaces: true,
+            eos_to_newline: false,
+            pending: Vec::new(),
+            clean_up_tail: String::new(),
+        }
+    }
+
+    /// Enable or disable `clean_up_tokenization_spaces`-style whitespace cleanup. Defaults to
+    /// `true`.
+    #[must_use]
+    pub fn with_clean_up_tokenization_spaces(mut self, clean_up: bool) -> Self {
+        self.clean_up_tokenization_spaces = clean_up;
+        self
+    }
+
+    /// Opt in to substituting the EOS token with a newline, matching older example code. Off by
+    /// default.
+    #[must_use]
+    pub fn with_eos_to_newline(mut self, eos_to_newline: bool) -> Self {
+        self.eos_to_newline = eos_to_newline;
+        self
+    }
+
+    /// Control whether special/control tokens are rendered as their literal text (`true`,
+    /// the default) or suppressed from the output entirely (`false`).
+    #[must_use]
+    pub fn with_render_special(mut self, render_special: bool) -> Self {
+        self.render_special = render_special;
+        self
+    }
+
+    /// Feed a single token into the detokenizer, returning the complete, valid UTF-8 text it
+    /// produced. Any trailing partial multi-byte sequence is buffered and returned once a later
+    /// token completes it, so streaming callers never see a truncated character.
+    pub fn push(&mut self, token: LlamaToken) -> String {
+        let is_special_piece = self.model.is_special_or_control_token(token);
+
+        if self.eos_to_newline && token == self.model.token_eos() {
+            self.remove_leading_space = false;
+            return self.clean_up("\n".to_string());
+        }
+
+        let mut bytes = match self
+            .model
+            .token_to_bytes(token, self.special, self.render_special)
+        {
+            Ok(bytes) => bytes,
+            Err(_) => return String::new(),
+        };
+
+        if !is_special_piece && self.remove_leading_space && bytes.first() == Some(&b' ') {
+            bytes.remove(0);
+        }
+        // Only the first token pushed is considered for stripping; a special first token uses up
+        // that chance without being stripped.
+        self.remove_leading_space = false;
+
+        self.pending.append(&mut bytes);
+        let (text, consumed) = lossy_utf8_prefix(&self.pending);
+        self.pending.drain(..consumed);
+
+        self.clean_up(text)
+    }
+
+    /// Run `clean_up_tokenization_spaces` across the boundary with the previous piece by
+    /// prepending whatever trailing text was held back last time, then holding back the new
+    /// trailing [`CLEAN_UP_TAIL_LEN`] characters in case the *next* piece completes a pattern.
+    fn clean_up(&mut self, text: String) -> String {
+        if !self.clean_up_tokenization_spaces {
+            return text;
+        }
+        let mut combined = std::mem::take(&mut self.clean_up_tail);
+        combined.push_str(&text);
+        let cleaned = clean_up_spaces(&combined);
+        split_tail(cleaned, CLEAN_UP_TAIL_LEN, &mut self.clean_up_tail)
+    }
+
+    /// Flush any buffered partial byte sequence (replacing it with `\u{FFFD}`) and any text held
+    /// back by [`Self::clean_up`] for a pattern that never arrived. Call this once generation has
+    /// finished to avoid silently dropping a truncated trailing token or an unflushed tail.
+    pub fn finish(&mut self) -> String {
+        let mut out = if self.pending.is_empty() {
+            String::new()
+        } else {
+            String::from_utf8_lossy(&std::mem::take(&mut self.pending)).into_owned()
+        };
+        out.push_str(&std::mem::take(&mut self.clean_up_tail));
+        out
+    }
+
+    /// Detokenize a whole slice of tokens at once.
+    #[must_use]
+    pub fn detokenize(model: &'model LlamaModel, special: Special, tokens: &[LlamaToken]) -> String {
+        let mut detokenizer = Self::new(model, special);
+        let mut out = String::new();
+        for &token in tokens {
+            out.push_str(&detokenizer.push(token));
+        }
+        out.push_str(&detokenizer.finish());
+        out
+    }
+}
+
+/// How many trailing characters [`LlamaDetokenizer::clean_up`] holds back across a `push` call,
+/// long enough to cover the longest `clean_up_spaces` pattern (`" n't"`/`" 've"`/`" 're"`, 4
+/// characters) minus one, since that's all another character arriving can still complete.
+const CLEAN_UP_TAIL_LEN: usize = 3;
+
+/// Split the last `tail_len` characters of `text` off into `tail`, returning the rest. If `text`
+/// is `tail_len` characters or shorter, the whole thing is held back and an empty string is
+/// returned.
+fn split_tail(text: String, tail_len: usize, tail: &mut String) -> String {
+    let char_count = text.chars().count();
+    if char_count <= tail_len {
+        *tail = text;
+        return String::new();
+    }
+    let split = text
+        .char_indices()
+        .nth(char_count - tail_len)
+        .map_or(text.len(), |(i, _)| i);
+    let mut text = text;
+    *tail = text.split_off(split);
+    text
+}
+
+/// Split `bytes` into the longest valid UTF-8 prefix (lossily decoded, with `\u{FFFD}` standing
+/// in for any invalid sequences) and the number of bytes consumed, leaving behind only a
+/// possibly-incomplete trailing multi-byte sequence.
+fn lossy_utf8_prefix(bytes: &[u8]) -> (String, usize) {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => (s.to_string(), bytes.len()),
+        Err(e) => {
+            let valid_up_to = e.valid_up_to();
+            let text = String::from_utf8_lossy(&bytes[..valid_up_to]).into_owned();
+            match e.error_len() {
+                // An incomplete sequence at the very end: keep buffering.
+                None => (text, valid_up_to),
+                // An invalid sequence, not just incomplete: replace it and move past it.
+                Some(invalid_len) => {
+                    let mut text = text;
+                    text.push('\u{FFFD}');
+                    let resume = valid_up_to + invalid_len;
+                    let (rest, rest_consumed) = lossy_utf8_prefix(&bytes[resume..]);
+                    text.push_str(&rest);
+                    (text, resume + rest_consumed)
+                }
+            }
+        }
+    }
+}
+
+/// Collapse spaces that the tokenizer inserted before punctuation, matching Hugging Face's
+/// `clean_up_tokenization_spaces`.
+fn clean_up_spaces(text: &str) -> String {
+    text.replace(" .", ".")
+        .replace(" ?", "?")
+        .replace(" !", "!")
+        .replace(" ,", ",")
+        .replace(" ' ", "'")
+        .replace(" n't", "n't")
+        .replace(" 'm", "'m")
+        .replace(" 's", "'s")
+        .replace(" 've", "'ve")
+        .replace(" 're", "'re")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lossy_utf8_prefix_buffers_incomplete_sequence() {
+        let euro = "€".as_bytes(); // 3 bytes: 0xE2 0x82 0xAC
+        let (text, consumed) = lossy_utf8_prefix(&euro[..2]);
+        assert_eq!(text, "");
+        assert_eq!(consumed, 0);
+
+        let mut pending = euro[..2].to_vec();
+        pending.push(euro[2]);
+        let (text, consumed) = lossy_utf8_prefix(&pending);
+        assert_eq!(text, "€");
+        assert_eq!(consumed, 3);
+    }
+
+    #[test]
+    fn lossy_utf8_prefix_replaces_invalid_byte() {
+        let (text, consumed) = lossy_utf8_prefix(&[b'a', 0xFF, b'b']);
+        assert_eq!(text, "a\u{FFFD}b");
+        assert_eq!(consumed, 3);
+    }
+
+    #[test]
+    fn clean_up_spaces_collapses_patterns_within_one_call() {
+        assert_eq!(clean_up_spaces("Hello ."), "Hello.");
+        assert_eq!(clean_up_spaces("I do n't know"), "I don't know");
+    }
+
+    #[test]
+    fn split_tail_holds_back_short_text_entirely() {
+        let mut tail = String::new();
+        let emitted = split_tail("abc".to_string(), CLEAN_UP_TAIL_LEN, &mut tail);
+        assert_eq!(emitted, "");
+        assert_eq!(tail, "abc");
+    }
+
+    #[test]
+    fn split_tail_emits_everything_but_the_trailing_window() {
+        let mut tail = String::new();
+        let emitted = split_tail("Hello wo".to_string(), CLEAN_UP_TAIL_LEN, &mut tail);
+        assert_eq!(emitted, "Hello");
+        assert_eq!(tail, " wo");
+    }
+
+    #[test]
+    fn clean_up_joins_a_pattern_split_across_two_pushes() {
+        // Simulates two tokens decoding to " " and "." separately: a naive per-call
+        // `clean_up_spaces` would see " " (no match) and "." (no match) and never collapse the
+        // space, even though the combined text is " ." which should become ".".
+        let mut tail = String::new();
+        let mut combined = std::mem::take(&mut tail);
+        combined.push_str(" ");
+        let cleaned = clean_up_spaces(&combined);
+        let first = split_tail(cleaned, CLEAN_UP_TAIL_LEN, &mut tail);
+        assert_eq!(first, "");
+        assert_eq!(tail, " ");
+
+        let mut combined = std::mem::take(&mut tail);
+        combined.push_str(".");
+        let cleaned = clean_up_spaces(&combined);
+        let second = split_tail(cleaned, CLEAN_UP_TAIL_LEN, &mut tail);
+        assert_eq!(format!("{first}{second}{tail}"), ".");
+    }
+}
@@ -0,0 +1,22 @@
+//! A safe wrapper around `llama_token`.
+
+pub mod detokenizer;
+
+/// A single token as used by `llama.cpp`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct LlamaToken(pub llama_cpp_sys_2::llama_token);
+
+impl LlamaToken {
+    /// Create a new `LlamaToken` from a raw id.
+    #[must_use]
+    pub fn new(token_id: i32) -> Self {
+        Self(token_id)
+    }
+
+    /// The raw token id.
+    #[must_use]
+    pub fn value(&self) -> i32 {
+        self.0
+    }
+}
@@ -0,0 +1,41 @@
+//! Types relating to a token's attributes, as reported by the GGUF vocabulary.
+
+/// A single attribute bit of a token, mirroring `llama_token_attr`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum LlamaTokenAttr {
+    /// No attributes set.
+    Undefined = llama_cpp_sys_2::LLAMA_TOKEN_ATTR_UNDEFINED as _,
+    /// The token represents an unknown/unseen piece of text.
+    Unknown = llama_cpp_sys_2::LLAMA_TOKEN_ATTR_UNKNOWN as _,
+    /// The token is unused.
+    Unused = llama_cpp_sys_2::LLAMA_TOKEN_ATTR_UNUSED as _,
+    /// The token is a normal piece of text.
+    Normal = llama_cpp_sys_2::LLAMA_TOKEN_ATTR_NORMAL as _,
+    /// The token is only produced/consumed by the tokenizer control flow, never surfaced as text.
+    Control = llama_cpp_sys_2::LLAMA_TOKEN_ATTR_CONTROL as _,
+    /// The token is a user-defined token.
+    UserDefined = llama_cpp_sys_2::LLAMA_TOKEN_ATTR_USER_DEFINED as _,
+    /// The token is a single byte.
+    Byte = llama_cpp_sys_2::LLAMA_TOKEN_ATTR_BYTE as _,
+    /// The token should not be split further.
+    Normalized = llama_cpp_sys_2::LLAMA_TOKEN_ATTR_NORMALIZED as _,
+    /// The token has a leading space stripped from it.
+    LStrip = llama_cpp_sys_2::LLAMA_TOKEN_ATTR_LSTRIP as _,
+    /// The token has a trailing space stripped from it.
+    RStrip = llama_cpp_sys_2::LLAMA_TOKEN_ATTR_RSTRIP as _,
+    /// The token should not have whitespace normalized around it.
+    SingleWord = llama_cpp_sys_2::LLAMA_TOKEN_ATTR_SINGLE_WORD as _,
+}
+
+/// The set of attributes attached to a single token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LlamaTokenAttrs(pub llama_cpp_sys_2::llama_token_attr);
+
+impl LlamaTokenAttrs {
+    /// Whether the attribute set contains `attr`.
+    #[must_use]
+    pub fn contains(&self, attr: LlamaTokenAttr) -> bool {
+        self.0 & (attr as llama_cpp_sys_2::llama_token_attr) != 0
+    }
+}